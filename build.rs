@@ -0,0 +1,96 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Classify an opcode's (high nibble, low byte) into the name of an `instruction::OpKind`
+// variant.
+//
+// Note: the table has no room for the vx nibble, so 00E0/00EE's additional requirement that
+// vx be zero is enforced by a dedicated check in `VM::peek_instruction` before it consults
+// this table, not here.
+fn classify(high: u16, low_byte: u16) -> &'static str {
+    let low_nibble = low_byte & 0x0F;
+
+    match high {
+        0x0 => match low_byte {
+            0xE0 => "Cls",
+            0xEE => "Ret",
+            _ => "Sys",
+        },
+        0x1 => "Jp",
+        0x2 => "Call",
+        0x3 => "SeByte",
+        0x4 => "SneByte",
+        0x5 => match low_nibble {
+            0x0 => "SeReg",
+            _ => "Unknown",
+        },
+        0x6 => "LdByte",
+        0x7 => "AddByte",
+        0x8 => match low_nibble {
+            0x0 => "LdReg",
+            0x1 => "OrReg",
+            0x2 => "AndReg",
+            0x3 => "XorReg",
+            0x4 => "AddReg",
+            0x5 => "SubReg",
+            0x6 => "Shr",
+            0x7 => "Subn",
+            0xE => "Shl",
+            _ => "Unknown",
+        },
+        0x9 => match low_nibble {
+            0x0 => "SneReg",
+            _ => "Unknown",
+        },
+        0xA => "LdI",
+        0xB => "JpV0",
+        0xC => "Rnd",
+        0xD => "Drw",
+        0xE => match low_byte {
+            0x9E => "Skp",
+            0xA1 => "Sknp",
+            _ => "Unknown",
+        },
+        0xF => match low_byte {
+            0x07 => "LdVxDt",
+            0x0A => "LdVxK",
+            0x15 => "LdDtVx",
+            0x18 => "LdStVx",
+            0x1E => "AddIVx",
+            0x29 => "LdFVx",
+            0x33 => "LdBVx",
+            0x55 => "LdIVx",
+            0x65 => "LdVxI",
+            _ => "Unknown",
+        },
+        _ => "Unknown",
+    }
+}
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let dest_path = Path::new(&out_dir).join("opcode_table.rs");
+
+    // Indexed by (high nibble << 8) | low byte, the bits that actually discriminate an
+    // instruction family; the middle nibble is left out (apart from 00E0/00EE, see `classify`,
+    // it is only ever an operand).
+    let mut entries = String::new();
+
+    for high in 0..16u16 {
+        for low_byte in 0..256u16 {
+            entries.push_str("    crate::instruction::OpKind::");
+            entries.push_str(classify(high, low_byte));
+            entries.push_str(",\n");
+        }
+    }
+
+    let generated = format!(
+        "pub static OPCODE_TABLE: [crate::instruction::OpKind; 4096] = [\n{}];\n",
+        entries
+    );
+
+    fs::write(&dest_path, generated).unwrap();
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
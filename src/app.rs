@@ -0,0 +1,275 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+use crate::audio::AudioSystem;
+use crate::chip8::VM;
+use crate::debugger::Debugger;
+use crate::savestate;
+
+// Target frame rate for the emulation loop
+const FRAME_RATE: u32 = 60;
+
+// Map a physical key to its CHIP-8 hex keypad equivalent
+//
+//   1 2 3 4        1 2 3 C
+//   Q W E R   ->   4 5 6 D
+//   A S D F        7 8 9 E
+//   Z X C V        A 0 B F
+fn map_key(keycode: Keycode) -> Option<u8> {
+    match keycode {
+        Keycode::Num1 => Some(0x1),
+        Keycode::Num2 => Some(0x2),
+        Keycode::Num3 => Some(0x3),
+        Keycode::Num4 => Some(0xC),
+        Keycode::Q => Some(0x4),
+        Keycode::W => Some(0x5),
+        Keycode::E => Some(0x6),
+        Keycode::R => Some(0xD),
+        Keycode::A => Some(0x7),
+        Keycode::S => Some(0x8),
+        Keycode::D => Some(0x9),
+        Keycode::F => Some(0xE),
+        Keycode::Z => Some(0xA),
+        Keycode::X => Some(0x0),
+        Keycode::C => Some(0xB),
+        Keycode::V => Some(0xF),
+        _ => None,
+    }
+}
+
+// The SDL2-backed front end: owns the window, drives the VM, and forwards input
+pub struct App {
+    canvas: Canvas<Window>,
+    event_pump: sdl2::EventPump,
+    audio: AudioSystem,
+    scale: u32,
+    rom_name: String,
+    paused: bool,
+    cycles_per_frame: u32,
+}
+
+impl App {
+    // Open a window sized for a 64x32 framebuffer scaled up by `scale`, running
+    // `cycles_per_frame` VM cycles per rendered frame
+    pub fn new(sdl_context: &sdl2::Sdl, scale: u32, rom_name: String, cycles_per_frame: u32) -> App {
+        let video = sdl_context.video().unwrap();
+
+        let window = video
+            .window("chip8", 64 * scale, 32 * scale)
+            .position_centered()
+            .build()
+            .unwrap();
+
+        let canvas = window.into_canvas().build().unwrap();
+        let event_pump = sdl_context.event_pump().unwrap();
+        let audio = AudioSystem::new(sdl_context);
+
+        App {
+            canvas,
+            event_pump,
+            audio,
+            scale,
+            rom_name,
+            paused: false,
+            cycles_per_frame,
+        }
+    }
+
+    // Run the VM until the window is closed or a trap halts it
+    pub fn run(&mut self, debugger: &mut Debugger) {
+        let frame_time = Duration::from_secs(1) / FRAME_RATE;
+
+        'running: loop {
+            let frame_start = Instant::now();
+
+            let events: Vec<Event> = self.event_pump.poll_iter().collect();
+            for event in events {
+                match event {
+                    Event::Quit { .. } => break 'running,
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F5),
+                        ..
+                    } => {
+                        if let Ok(path) =
+                            savestate::save(&self.rom_name, &debugger.vm().save_state())
+                        {
+                            println!("Saved state to {}", path.display());
+                        }
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F9),
+                        ..
+                    } => {
+                        if let Some(state) = savestate::load_latest(Path::new("."), &self.rom_name) {
+                            if let Err(trap) = debugger.vm_mut().load_state(&state) {
+                                eprintln!("Failed to load save state: {:?}", trap);
+                            }
+                        }
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F6),
+                        ..
+                    } => {
+                        let pc = debugger.vm().program_counter();
+                        debugger.add_breakpoint(pc);
+                        println!("Breakpoint set at {:#06X}", pc);
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F7),
+                        ..
+                    } => {
+                        let pc = debugger.vm().program_counter();
+                        debugger.remove_breakpoint(pc);
+                        println!("Breakpoint cleared at {:#06X}", pc);
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F10),
+                        ..
+                    } => {
+                        self.paused = !self.paused;
+                        if self.paused {
+                            self.print_debug_state(debugger);
+                        }
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F11),
+                        ..
+                    } if self.paused => {
+                        if let Err(trap) = debugger.step() {
+                            eprintln!("Trap: {:?}", trap);
+                        }
+                        self.print_debug_state(debugger);
+                    }
+                    Event::KeyDown {
+                        keycode: Some(keycode),
+                        ..
+                    } => {
+                        if let Some(key) = map_key(keycode) {
+                            let _ = debugger.vm_mut().key(key, true);
+                        }
+                    }
+                    Event::KeyUp {
+                        keycode: Some(keycode),
+                        ..
+                    } => {
+                        if let Some(key) = map_key(keycode) {
+                            let _ = debugger.vm_mut().key(key, false);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+
+            if !self.paused {
+                for _ in 0..self.cycles_per_frame {
+                    if let Err(trap) = debugger.step() {
+                        eprintln!("Halted on unrecoverable trap: {:?}", trap);
+                        self.print_debug_state(debugger);
+                        break 'running;
+                    }
+
+                    if debugger.at_breakpoint() {
+                        println!("Hit breakpoint at {:#06X}", debugger.vm().program_counter());
+                        self.paused = true;
+                        self.print_debug_state(debugger);
+                        break;
+                    }
+                }
+            }
+
+            debugger.vm_mut().tick_timers();
+            self.audio
+                .update(debugger.vm().buzzer_active(), frame_time.as_secs_f32());
+
+            self.draw(debugger.vm());
+
+            let elapsed = frame_start.elapsed();
+            if elapsed < frame_time {
+                std::thread::sleep(frame_time - elapsed);
+            }
+        }
+    }
+
+    // Print a window of disassembly around the program counter, plus register/stack state.
+    // Shown when paused, on breakpoint hit, and on an unrecoverable trap.
+    fn print_debug_state(&self, debugger: &Debugger) {
+        let vm = debugger.vm();
+
+        println!("--- PC: {:#06X}  I: {:#06X} ---", vm.program_counter(), vm.index_register());
+        println!("Registers: {:02X?}", vm.registers());
+        println!(
+            "Stack[{}]: {:04X?}",
+            vm.stack_pointer(),
+            &vm.stack()[..vm.stack_pointer() as usize]
+        );
+
+        let mem_start = vm.index_register() as usize & !0xF;
+        let mem_end = (mem_start + 16).min(vm.memory().len());
+        println!(
+            "Memory[{:#06X}..{:#06X}]: {:02X?}",
+            mem_start,
+            mem_end,
+            &vm.memory()[mem_start..mem_end]
+        );
+
+        for (address, mnemonic) in debugger.disassemble_window(5, 10) {
+            let marker = if address == vm.program_counter() { "->" } else { "  " };
+            println!("{} {:#06X}  {}", marker, address, mnemonic);
+        }
+    }
+
+    // Blit the VM's framebuffer to the window, scaled up
+    fn draw(&mut self, vm: &VM) {
+        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
+        self.canvas.clear();
+
+        self.canvas.set_draw_color(Color::RGB(255, 255, 255));
+
+        for y in 0..32 {
+            for x in 0..64 {
+                if vm.framebuffer().get(x, y) {
+                    let rect = Rect::new(
+                        (x as u32 * self.scale) as i32,
+                        (y as u32 * self.scale) as i32,
+                        self.scale,
+                        self.scale,
+                    );
+
+                    self.canvas.fill_rect(rect).unwrap();
+                }
+            }
+        }
+
+        self.canvas.present();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_key_covers_the_full_hex_keypad() {
+        assert_eq!(map_key(Keycode::Num1), Some(0x1));
+        assert_eq!(map_key(Keycode::Num4), Some(0xC));
+        assert_eq!(map_key(Keycode::Q), Some(0x4));
+        assert_eq!(map_key(Keycode::R), Some(0xD));
+        assert_eq!(map_key(Keycode::A), Some(0x7));
+        assert_eq!(map_key(Keycode::F), Some(0xE));
+        assert_eq!(map_key(Keycode::Z), Some(0xA));
+        assert_eq!(map_key(Keycode::V), Some(0xF));
+    }
+
+    #[test]
+    fn map_key_rejects_keys_outside_the_keypad() {
+        assert_eq!(map_key(Keycode::Space), None);
+        assert_eq!(map_key(Keycode::Escape), None);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+
+// Samples per second the device is opened at
+const SAMPLE_RATE: i32 = 44100;
+
+// Frequency of the buzzer tone
+const TONE_HZ: f32 = 440.0;
+
+// Cutoff of the one-pole low-pass filter that smooths the tone's start/stop edges
+const FILTER_CUTOFF_HZ: f32 = 3000.0;
+
+// Raw square-wave samples produced by the main thread and consumed by the audio callback
+type SharedRing = Arc<Mutex<VecDeque<f32>>>;
+
+// One-pole low-pass filter: y[n] = y[n-1] + alpha * (x[n] - y[n-1])
+struct OnePoleFilter {
+    alpha: f32,
+    state: f32,
+}
+
+impl OnePoleFilter {
+    fn new(cutoff_hz: f32, sample_rate: f32) -> OnePoleFilter {
+        let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff_hz);
+        let dt = 1.0 / sample_rate;
+        let alpha = dt / (rc + dt);
+
+        OnePoleFilter { alpha, state: 0.0 }
+    }
+
+    fn apply(&mut self, sample: f32) -> f32 {
+        self.state += self.alpha * (sample - self.state);
+        self.state
+    }
+}
+
+// Consumes buffered square-wave samples, filtering out the clicks at tone start/stop
+struct Buzzer {
+    ring: SharedRing,
+    filter: OnePoleFilter,
+}
+
+impl AudioCallback for Buzzer {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        let mut ring = self.ring.lock().unwrap();
+
+        for sample in out.iter_mut() {
+            let raw = ring.pop_front().unwrap_or(0.0);
+            *sample = self.filter.apply(raw);
+        }
+    }
+}
+
+// Drives the SDL2 audio device: fills the ring buffer with a square wave while the VM's
+// buzzer is active, and primes/pauses the device so playback never starts or stops mid-buffer
+pub struct AudioSystem {
+    device: AudioDevice<Buzzer>,
+    ring: SharedRing,
+    phase: f32,
+    playing: bool,
+    // One callback's worth of samples at the buffer size SDL actually negotiated, not one
+    // 60Hz frame's worth -- priming to anything smaller risks the callback draining
+    // the ring dry before `update` refills it, which is the click this system exists to avoid
+    prime_samples: usize,
+}
+
+impl AudioSystem {
+    pub fn new(sdl_context: &sdl2::Sdl) -> AudioSystem {
+        let audio = sdl_context.audio().unwrap();
+
+        let spec = AudioSpecDesired {
+            freq: Some(SAMPLE_RATE),
+            channels: Some(1),
+            samples: None,
+        };
+
+        let ring: SharedRing = Arc::new(Mutex::new(VecDeque::new()));
+        let callback_ring = Arc::clone(&ring);
+
+        let device = audio
+            .open_playback(None, &spec, |spec| Buzzer {
+                ring: callback_ring,
+                filter: OnePoleFilter::new(FILTER_CUTOFF_HZ, spec.freq as f32),
+            })
+            .unwrap();
+
+        let prime_samples = device.spec().samples as usize;
+
+        AudioSystem {
+            device,
+            ring,
+            phase: 0.0,
+            playing: false,
+            prime_samples,
+        }
+    }
+
+    // Called once per frame with the VM's current buzzer state
+    pub fn update(&mut self, active: bool, frame_seconds: f32) {
+        let mut ring = self.ring.lock().unwrap();
+
+        if active {
+            let samples_needed = (SAMPLE_RATE as f32 * frame_seconds) as usize;
+            let step = TONE_HZ / SAMPLE_RATE as f32;
+
+            for _ in 0..samples_needed {
+                let value = if self.phase < 0.5 { 1.0 } else { -1.0 };
+                ring.push_back(value);
+
+                self.phase += step;
+                if self.phase >= 1.0 {
+                    self.phase -= 1.0;
+                }
+            }
+
+            if !self.playing && ring.len() >= self.prime_samples {
+                self.device.resume();
+                self.playing = true;
+            }
+        } else if ring.is_empty() && self.playing {
+            self.device.pause();
+            self.playing = false;
+            self.phase = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_pole_filter_first_sample_moves_by_alpha_from_rest() {
+        let mut filter = OnePoleFilter::new(FILTER_CUTOFF_HZ, SAMPLE_RATE as f32);
+        let alpha = filter.alpha;
+        assert_eq!(filter.apply(1.0), alpha); // state starts at 0, so the first step is alpha * (1 - 0)
+    }
+
+    #[test]
+    fn one_pole_filter_converges_to_a_sustained_input() {
+        let mut filter = OnePoleFilter::new(FILTER_CUTOFF_HZ, SAMPLE_RATE as f32);
+
+        let mut last = 0.0;
+        for _ in 0..1000 {
+            last = filter.apply(1.0);
+        }
+
+        assert!((last - 1.0).abs() < 1e-3);
+    }
+}
@@ -1,5 +1,13 @@
 use rand::prelude::*;
 
+use crate::instruction::{self, Instruction};
+use crate::quirks::Quirks;
+use crate::trap::{Trap, TrapAction};
+
+// Maps a (high nibble, low byte) opcode key to its instruction family. Generated by build.rs
+// from the decode rules in its `classify` function.
+include!(concat!(env!("OUT_DIR"), "/opcode_table.rs"));
+
 pub struct VM {
     // Memory
     memory: [u8; 4096],
@@ -27,11 +35,17 @@ pub struct VM {
 
     // Random
     rng: ThreadRng,
+
+    // Compatibility toggles for opcodes that are ambiguous between interpreter generations
+    quirks: Quirks,
+
+    // Invoked whenever `cycle` raises a `Trap`, to decide whether to halt, skip, or continue
+    trap_handler: Option<Box<dyn FnMut(Trap) -> TrapAction>>,
 }
 
 impl VM {
     // Initialize a new virtual machine
-    pub fn new() -> VM {
+    pub fn new(quirks: Quirks) -> VM {
         VM {
             memory: [0; 4096],
             stack: [0; 16],
@@ -44,6 +58,8 @@ impl VM {
             sound_timer: 0,
             keyboard: [false; 16],
             rng: thread_rng(),
+            quirks,
+            trap_handler: None,
         }
     }
 
@@ -54,203 +70,398 @@ impl VM {
         }
     }
 
-    // Simulate a CPU cycle
-    pub fn cycle(&mut self) {
+    // Register a handler that decides what happens when `cycle` raises a `Trap`. Without one,
+    // a trap always halts (`cycle` returns `Err`).
+    pub fn on_trap<F: FnMut(Trap) -> TrapAction + 'static>(&mut self, handler: F) {
+        self.trap_handler = Some(Box::new(handler));
+    }
+
+    // Simulate a CPU cycle. Returns the trap that halted execution, if any.
+    pub fn cycle(&mut self) -> Result<(), Trap> {
+        let start_pc = self.program_counter;
+
         // Read the next instruction and execute it
-        let opcode = self.read_instruction();
+        let instruction = match self.read_instruction() {
+            Ok(instruction) => instruction,
+            Err(trap) => return self.handle_trap(trap, start_pc),
+        };
 
         // Increment the program counter (since instructions are 2 bytes long, we increment by 2)
         self.program_counter += 2;
 
-        self.op(opcode);
+        match self.op(instruction) {
+            Ok(()) => Ok(()),
+            Err(trap) => self.handle_trap(trap, start_pc),
+        }
     }
 
-    pub fn key(&mut self, key: u8, state: bool) {
-        if key >= 0xF {
-            panic!("Illegal key: {}", key);
+    // Run a raised trap through the registered handler, if any, to decide whether `cycle`
+    // should halt, force the program counter past the faulting instruction (Skip), or leave
+    // the program counter exactly where the fault left it and keep running (Continue) -- for
+    // a fault raised during decode (before the program counter advances) that resumes at the
+    // same instruction, while a fault raised during execution (after it advances) behaves
+    // like Skip since there's nothing left to rewind.
+    fn handle_trap(&mut self, trap: Trap, start_pc: u16) -> Result<(), Trap> {
+        match self.trap_handler.as_mut() {
+            Some(handler) => match handler(trap) {
+                TrapAction::Halt => Err(trap),
+                TrapAction::Skip => {
+                    self.program_counter = start_pc.wrapping_add(2);
+                    Ok(())
+                }
+                TrapAction::Continue => Ok(()),
+            },
+            None => Err(trap),
+        }
+    }
+
+    pub fn key(&mut self, key: u8, state: bool) -> Result<(), Trap> {
+        if key > 0xF {
+            return Err(Trap::IllegalKey(key));
         }
 
-        let key = key as usize;
+        self.keyboard[key as usize] = state;
 
-        self.keyboard[key] = state;
+        Ok(())
     }
 
-    // Read an instruction
-    fn read_instruction(&self) -> u16 {
-        let index = self.program_counter as usize;
+    // Expose the current framebuffer so a front end can render it
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
 
-        return ((self.memory[index] as u16) << 8) | (self.memory[index + 1] as u16);
+    // Decrement the delay and sound timers. Should be driven at 60 Hz by the front end
+    pub fn tick_timers(&mut self) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
     }
 
-    // Process an opcode
-    fn op(&mut self, opcode: u16) {
-        // Break the opcode into a tuple of individual nibbles
-        let parts = (
-            (opcode & 0xF000) >> 12,
-            (opcode & 0x0F00) >> 8,
-            (opcode & 0x00F0) >> 4,
-            (opcode & 0x000F),
-        );
+    // Whether the buzzer should currently be sounding
+    pub fn buzzer_active(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    // Exact byte length of a `save_state` blob: memory + stack + stack_pointer +
+    // program_counter + registers + index_register + delay_timer + sound_timer + keyboard +
+    // framebuffer. `load_state` rejects anything else rather than indexing blindly into it.
+    const STATE_LEN: usize = 4096 + (16 * 2) + 1 + 2 + 16 + 2 + 1 + 1 + 16 + (32 * 8);
+
+    // Serialize the full machine state (everything but `rng`) into a compact binary blob
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(Self::STATE_LEN);
+
+        state.extend_from_slice(&self.memory);
+
+        for value in self.stack.iter() {
+            state.extend_from_slice(&value.to_be_bytes());
+        }
+
+        state.push(self.stack_pointer);
+        state.extend_from_slice(&self.program_counter.to_be_bytes());
+        state.extend_from_slice(&self.registers);
+        state.extend_from_slice(&self.index_register.to_be_bytes());
+        state.push(self.delay_timer);
+        state.push(self.sound_timer);
+
+        for key in self.keyboard.iter() {
+            state.push(*key as u8);
+        }
+
+        for row in self.framebuffer.buffer.iter() {
+            state.extend_from_slice(&row.to_be_bytes());
+        }
+
+        state
+    }
+
+    // Restore a machine state produced by `save_state`. `rng` is re-seeded, not restored.
+    // Rejects anything that isn't exactly `STATE_LEN` bytes instead of indexing blindly into
+    // a truncated or foreign blob.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), Trap> {
+        if state.len() != Self::STATE_LEN {
+            return Err(Trap::CorruptSaveState);
+        }
+
+        let mut cursor = 0;
+
+        let mut take = |len: usize| {
+            let slice = &state[cursor..cursor + len];
+            cursor += len;
+            slice
+        };
+
+        self.memory.copy_from_slice(take(4096));
+
+        for value in self.stack.iter_mut() {
+            *value = u16::from_be_bytes(take(2).try_into().unwrap());
+        }
+
+        self.stack_pointer = take(1)[0];
+        self.program_counter = u16::from_be_bytes(take(2).try_into().unwrap());
+        self.registers.copy_from_slice(take(16));
+        self.index_register = u16::from_be_bytes(take(2).try_into().unwrap());
+        self.delay_timer = take(1)[0];
+        self.sound_timer = take(1)[0];
+
+        for key in self.keyboard.iter_mut() {
+            *key = take(1)[0] != 0;
+        }
+
+        for row in self.framebuffer.buffer.iter_mut() {
+            *row = u64::from_be_bytes(take(8).try_into().unwrap());
+        }
+
+        self.rng = thread_rng();
+
+        Ok(())
+    }
+
+    // Read and decode the next instruction
+    fn read_instruction(&self) -> Result<Instruction, Trap> {
+        self.peek_instruction(self.program_counter)
+    }
+
+    // Decode the instruction at an arbitrary address without advancing the program counter.
+    // Used by the disassembler/debugger to preview memory without mutating machine state.
+    pub fn peek_instruction(&self, address: u16) -> Result<Instruction, Trap> {
+        self.check_memory_bounds(address, 2)?;
+
+        let index = address as usize;
+        let opcode = ((self.memory[index] as u16) << 8) | (self.memory[index + 1] as u16);
+
+        // 00E0/00EE are only Cls/Ret when the vx nibble is also zero; with a non-zero vx
+        // they're SYS calls we treat as a no-op, same as any other 0NNN. The table is keyed
+        // on (high, low byte) alone, so just these two low bytes need the vx nibble checked
+        // before falling back to it.
+        let low_byte = opcode & 0x00FF;
+        if opcode & 0xF000 == 0x0000 && opcode & 0x0F00 != 0x0000 && (low_byte == 0xE0 || low_byte == 0xEE) {
+            return Ok(instruction::decode(opcode, instruction::OpKind::Sys));
+        }
+
+        // Past the 00E0/00EE check above, the high nibble plus the low byte are the only bits
+        // that select an instruction family; the middle nibble is only an operand.
+        let key = (((opcode & 0xF000) >> 4) | (opcode & 0x00FF)) as usize;
+        let kind = OPCODE_TABLE[key];
+
+        Ok(instruction::decode(opcode, kind))
+    }
+
+    // Read-only introspection for the debugger
+
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.registers
+    }
+
+    pub fn index_register(&self) -> u16 {
+        self.index_register
+    }
+
+    pub fn stack(&self) -> &[u16; 16] {
+        &self.stack
+    }
+
+    pub fn stack_pointer(&self) -> u8 {
+        self.stack_pointer
+    }
+
+    pub fn memory(&self) -> &[u8; 4096] {
+        &self.memory
+    }
+
+    // Fail with `MemoryOutOfBounds` if `[start, start + len)` doesn't fit in `memory`
+    fn check_memory_bounds(&self, start: u16, len: u16) -> Result<(), Trap> {
+        if start as usize + len as usize > self.memory.len() {
+            Err(Trap::MemoryOutOfBounds)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Execute a decoded instruction
+    fn op(&mut self, instruction: Instruction) -> Result<(), Trap> {
+        match instruction {
+            Instruction::Cls => self.framebuffer.clear(),
+            Instruction::Ret => {
+                if self.stack_pointer == 0 {
+                    return Err(Trap::StackUnderflow);
+                }
 
-        // Pre compute arguments for ease of use
-        let nnn = opcode & 0x0FFF;
-        let vx = ((opcode & 0x0F00) >> 8) as usize;
-        let vy = ((opcode & 0x00F0) >> 4) as usize;
-        let byte = (opcode & 0x00FF) as u8;
-
-        match parts {
-            // CLS
-            (0, 0, 0xE, 0) => self.framebuffer.clear(),
-            // RET
-            (0, 0, 0xE, 0xE) => {
                 self.stack_pointer -= 1;
                 self.program_counter = self.stack[self.stack_pointer as usize];
             }
-            // AND Vx, Vy
-            (0, _, _, 2) => self.registers[vx] &= self.registers[vy],
-            // SYS addr
-            (0, _, _, _) => (),
-            // JP addr
-            (1, _, _, _) => self.program_counter = nnn,
-            // CALL addr
-            (2, _, _, _) => {
-                self.stack_pointer += 1;
+            Instruction::AndReg { vx, vy } => self.registers[vx] &= self.registers[vy],
+            Instruction::Sys => (),
+            Instruction::Jp { nnn } => self.program_counter = nnn,
+            Instruction::Call { nnn } => {
+                if self.stack_pointer as usize >= self.stack.len() {
+                    return Err(Trap::StackOverflow);
+                }
+
                 self.stack[self.stack_pointer as usize] = self.program_counter;
+                self.stack_pointer += 1;
                 self.program_counter = nnn;
             }
-            // SE Vx, byte
-            (3, _, _, _) => {
+            Instruction::SeByte { vx, byte } => {
                 if self.registers[vx] == byte {
                     self.program_counter += 2;
                 }
             }
-            // SNE Vx, byte
-            (4, _, _, _) => {
+            Instruction::SneByte { vx, byte } => {
                 if self.registers[vx] != byte {
                     self.program_counter += 2;
                 }
             }
-            // SE Vx, Vy
-            (5, _, _, _) => {
+            Instruction::SeReg { vx, vy } => {
                 if self.registers[vx] == self.registers[vy] {
                     self.program_counter += 2;
                 }
             }
-            // LD Vx, byte
-            (6, _, _, _) => self.registers[vx] = byte,
-            // ADD Vx, byte
-            (7, _, _, _) => self.registers[vx] += byte,
-            // LD Vx, Vy
-            (8, _, _, 0) => self.registers[vx] = self.registers[vy],
-            // OR Vx, Vy
-            (8, _, _, 1) => self.registers[vx] |= self.registers[vy],
-            // XOR Vx, Vy
-            (8, _, _, 3) => self.registers[vx] ^= self.registers[vy],
-            // ADD Vx, Vy
-            (8, _, _, 4) => {
+            Instruction::LdByte { vx, byte } => self.registers[vx] = byte,
+            Instruction::AddByte { vx, byte } => {
+                self.registers[vx] = self.registers[vx].wrapping_add(byte)
+            }
+            Instruction::LdReg { vx, vy } => self.registers[vx] = self.registers[vy],
+            Instruction::OrReg { vx, vy } => self.registers[vx] |= self.registers[vy],
+            Instruction::XorReg { vx, vy } => self.registers[vx] ^= self.registers[vy],
+            Instruction::AddReg { vx, vy } => {
                 let sum = (self.registers[vx] as u16) + (self.registers[vy] as u16);
 
                 self.registers[0xF] = (sum > 255) as u8;
                 self.registers[vx] = sum as u8 & 0xFF;
             }
-            // SUB Vx, Vy
-            (8, _, _, 5) => {
+            Instruction::SubReg { vx, vy } => {
                 self.registers[0xF] = (self.registers[vx] > self.registers[vy]) as u8;
-                self.registers[vx] -= self.registers[vy];
+                self.registers[vx] = self.registers[vx].wrapping_sub(self.registers[vy]);
             }
-            // SHR Vx
-            (8, _, _, 6) => {
+            Instruction::Shr { vx, vy } => {
+                if !self.quirks.shift_in_place {
+                    self.registers[vx] = self.registers[vy];
+                }
+
                 self.registers[0xF] = self.registers[vx] & 0x1;
-                self.registers[vx] >>= 1;
+                self.registers[vx] = self.registers[vx].wrapping_shr(1);
             }
-            // SUBN Vx, Vy
-            (8, _, _, 7) => {
+            Instruction::Subn { vx, vy } => {
                 self.registers[0xF] = (self.registers[vy] > self.registers[vx]) as u8;
-                self.registers[vx] = self.registers[vy] - self.registers[vx];
+                self.registers[vx] = self.registers[vy].wrapping_sub(self.registers[vx]);
             }
-            // SHL Vx, Vy
-            (8, _, _, 8) => {
+            Instruction::Shl { vx, vy } => {
+                if !self.quirks.shift_in_place {
+                    self.registers[vx] = self.registers[vy];
+                }
+
                 self.registers[0xF] = (self.registers[vx] & 0x80) >> 7;
-                self.registers[vx] <<= 1;
+                self.registers[vx] = self.registers[vx].wrapping_shl(1);
             }
-            // SNE Vx, Vy
-            (9, _, _, 0) => {
+            Instruction::SneReg { vx, vy } => {
                 if self.registers[vx] != self.registers[vy] {
                     self.program_counter += 2;
                 }
             }
-            // LD I, addr
-            (0xA, _, _, _) => self.index_register = nnn,
-            // JP V0, addr
-            (0xB, _, _, _) => self.program_counter = (self.registers[0] as u16) + nnn,
-            // RND Vx, byte
-            (0xC, _, _, _) => {
+            Instruction::LdI { nnn } => self.index_register = nnn,
+            Instruction::JpV0 { vx, nnn } => {
+                let base = if self.quirks.jump_uses_vx {
+                    self.registers[vx]
+                } else {
+                    self.registers[0]
+                };
+
+                self.program_counter = (base as u16) + nnn;
+            }
+            Instruction::Rnd { vx, byte } => {
                 self.registers[vx] = (self.rng.gen_range(0, 255) as u8) & byte;
             }
-            // DRW Vx, Vy, nibble
-            (0xD, _, _, _) => {
-                let height = (opcode & 0x000F) as u8;
+            Instruction::Drw { vx, vy, n } => {
+                let height = n as u16;
 
-                let x_start = vx as u8 % 64;
-                let y_start = vy as u8 % 32;
+                self.check_memory_bounds(self.index_register, height)?;
+
+                let x_start = (self.registers[vx] % 64) as u16;
+                let y_start = (self.registers[vy] % 32) as u16;
 
                 let mut collision = false;
 
-                for x in x_start..(x_start + 8) {
-                    for y in y_start..(y_start + height) {
-                        let x = x as u16;
-                        let y = y as u16;
+                for row in 0..height {
+                    let byte = self.memory[(self.index_register + row) as usize];
+
+                    for col in 0..8u16 {
+                        let pixel = (byte & (0x80 >> col)) != 0;
+                        if !pixel {
+                            continue;
+                        }
+
+                        let (mut x, mut y) = (x_start + col, y_start + row);
 
-                        let pixel = (self.memory[(self.index_register + y) as usize] & (0x80 >> x)) != 0;
+                        if self.quirks.clip_sprites {
+                            if x >= 64 || y >= 32 {
+                                continue;
+                            }
+                        } else {
+                            x %= 64;
+                            y %= 32;
+                        }
 
-                        collision |= self.framebuffer.set(x as u8, y as u8, pixel);
+                        collision |= self.framebuffer.set(x as u8, y as u8, true);
                     }
                 }
 
                 self.registers[0xF] = collision as u8;
-            },
-            // SKP Vx
-            (0xE, _, 9, 0xE) => {
-                let key = self.registers[vx] as usize;
+            }
+            Instruction::Skp { vx } => {
+                let key = self.registers[vx];
+                if key > 0xF {
+                    return Err(Trap::IllegalKey(key));
+                }
 
-                if self.keyboard[key] {
+                if self.keyboard[key as usize] {
                     self.program_counter += 2;
                 }
-            },
-            // SKNP Vx
-            (0xE, _, 0xA, 0x1) => {
-                let key = self.registers[vx] as usize;
+            }
+            Instruction::Sknp { vx } => {
+                let key = self.registers[vx];
+                if key > 0xF {
+                    return Err(Trap::IllegalKey(key));
+                }
 
-                if !self.keyboard[key] {
+                if !self.keyboard[key as usize] {
                     self.program_counter += 2;
                 }
-            },
-            // LD Vx, DT
-            (0xF, _, 0, 7) => self.registers[vx] = self.delay_timer,
-            // LD Vx, K
-            (0xF, _, 0, 0xA) => {
+            }
+            Instruction::LdVxDt { vx } => self.registers[vx] = self.delay_timer,
+            Instruction::LdVxK { vx } => {
+                let mut found = false;
+
                 for (i, key) in self.keyboard.iter().enumerate() {
                     if *key {
                         self.registers[vx] = i as u8;
-                        return;
+                        found = true;
+                        break;
                     }
                 }
 
-                self.program_counter -= 2;
-            },
-            // LD DT, Vx
-            (0xF, _, 1, 5) => self.delay_timer = self.registers[vx],
-            // LD ST, Vx
-            (0xF, _, 1, 8) => self.sound_timer = self.registers[vx],
-            // ADD I, Vx
-            (0xF, _, 1, 0xE) => self.index_register += self.registers[vx] as u16,
-            // LD F, Vx
-            (0xF, _, 2, 9) => {
+                if !found {
+                    self.program_counter -= 2;
+                }
+            }
+            Instruction::LdDtVx { vx } => self.delay_timer = self.registers[vx],
+            Instruction::LdStVx { vx } => self.sound_timer = self.registers[vx],
+            Instruction::AddIVx { vx } => self.index_register += self.registers[vx] as u16,
+            Instruction::LdFVx { vx } => {
                 let digit = self.registers[vx];
                 self.index_register = (0x50 + (5 * digit)) as u16;
-            },
-            // LD B, Vx
-            (0xF, _, 3, 3) => {
+            }
+            Instruction::LdBVx { vx } => {
+                self.check_memory_bounds(self.index_register, 3)?;
+
                 let mut val = self.registers[vx];
                 let index = self.index_register as usize;
 
@@ -262,30 +473,37 @@ impl VM {
 
                 self.memory[index] = val % 10;
             }
-            // LD [I], Vx
-            (0xF, _, 5, 5) => {
-                for i in 0..vx {
-                    let i = i as usize;
+            Instruction::LdIVx { vx } => {
+                self.check_memory_bounds(self.index_register, vx as u16 + 1)?;
 
+                for i in 0..=vx {
                     self.memory[self.index_register as usize + i] = self.registers[i];
                 }
+
+                if self.quirks.load_store_increments_index {
+                    self.index_register += vx as u16 + 1;
+                }
             }
-            // LD Vx, [I]
-            (0xF, _, 6, 5) => {
-                for i in 0..vx {
-                    let i = i as usize;
+            Instruction::LdVxI { vx } => {
+                self.check_memory_bounds(self.index_register, vx as u16 + 1)?;
 
+                for i in 0..=vx {
                     self.registers[i] = self.memory[self.index_register as usize + i];
                 }
-            }
 
-            (_, _, _, _) => println!("Unknown opcode: {:#06x}", opcode),
+                if self.quirks.load_store_increments_index {
+                    self.index_register += vx as u16 + 1;
+                }
+            }
+            Instruction::Unknown { opcode } => return Err(Trap::IllegalOpcode(opcode)),
         }
+
+        Ok(())
     }
 }
 
 // 64x32 bit framebuffer. Essentially a bitfield; abstracted to make accessing individual pixels easier
-struct Framebuffer {
+pub struct Framebuffer {
     buffer: [u64; 32],
 }
 
@@ -318,7 +536,7 @@ impl Framebuffer {
     }
 
     // Get a current pixel's state
-    fn get(&self, x: u8, y: u8) -> bool {
+    pub fn get(&self, x: u8, y: u8) -> bool {
         // Convert varaibles to proper types for array indexing and bitwise ops
         let y = y as usize;
         let x = x as u64;
@@ -326,3 +544,241 @@ impl Framebuffer {
         return ((self.buffer[y] >> x) & 0x0001) != 0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_at(opcode: u16) -> Instruction {
+        let mut vm = VM::new(Quirks::default());
+        vm.memory[0x200] = (opcode >> 8) as u8;
+        vm.memory[0x201] = (opcode & 0xFF) as u8;
+        vm.peek_instruction(0x200).unwrap()
+    }
+
+    // 00E0/00EE are only Cls/Ret when vx is zero; verifies the vx check that
+    // `peek_instruction` makes before the table lookup, as the build.rs decoder comment says.
+    #[test]
+    fn decodes_00e0_00ee_only_when_vx_is_zero() {
+        assert!(matches!(decode_at(0x00E0), Instruction::Cls));
+        assert!(matches!(decode_at(0x00EE), Instruction::Ret));
+        assert!(matches!(decode_at(0x01E0), Instruction::Sys));
+        assert!(matches!(decode_at(0x0AEE), Instruction::Sys));
+    }
+
+    #[test]
+    fn decodes_and_reg() {
+        match decode_at(0x8132) {
+            Instruction::AndReg { vx, vy } => {
+                assert_eq!(vx, 1);
+                assert_eq!(vy, 3);
+            }
+            other => panic!("expected AndReg, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decodes_other_0nnn_as_sys() {
+        assert!(matches!(decode_at(0x0123), Instruction::Sys));
+    }
+
+    #[test]
+    fn save_state_round_trips() {
+        let mut vm = VM::new(Quirks::default());
+        vm.memory[0x300] = 0xAB;
+        vm.registers[3] = 0x42;
+        vm.index_register = 0x123;
+        vm.program_counter = 0x400;
+        vm.delay_timer = 7;
+        vm.sound_timer = 9;
+        vm.keyboard[5] = true;
+        vm.framebuffer.buffer[2] = 0xDEAD_BEEF;
+
+        let state = vm.save_state();
+
+        let mut restored = VM::new(Quirks::default());
+        restored.load_state(&state).unwrap();
+
+        assert_eq!(restored.memory[0x300], 0xAB);
+        assert_eq!(restored.registers[3], 0x42);
+        assert_eq!(restored.index_register, 0x123);
+        assert_eq!(restored.program_counter, 0x400);
+        assert_eq!(restored.delay_timer, 7);
+        assert_eq!(restored.sound_timer, 9);
+        assert!(restored.keyboard[5]);
+        assert_eq!(restored.framebuffer.buffer[2], 0xDEAD_BEEF);
+    }
+
+    #[test]
+    fn load_state_rejects_wrong_length() {
+        let mut vm = VM::new(Quirks::default());
+        assert_eq!(vm.load_state(&[0u8; 10]), Err(Trap::CorruptSaveState));
+    }
+
+    #[test]
+    fn shr_quirk_chooses_shift_in_place_vs_copy_then_shift() {
+        let mut vip = VM::new(Quirks::chip8());
+        vip.registers[1] = 10;
+        vip.registers[2] = 4;
+        vip.op(Instruction::Shr { vx: 1, vy: 2 }).unwrap();
+        assert_eq!(vip.registers[1], 2); // Vy (4) copied into Vx, then shifted
+
+        let mut schip = VM::new(Quirks::schip());
+        schip.registers[1] = 10;
+        schip.registers[2] = 4;
+        schip.op(Instruction::Shr { vx: 1, vy: 2 }).unwrap();
+        assert_eq!(schip.registers[1], 5); // Vx (10) shifted in place, Vy ignored
+    }
+
+    #[test]
+    fn jpv0_quirk_chooses_v0_vs_vx_as_jump_base() {
+        let mut vip = VM::new(Quirks::chip8());
+        vip.registers[0] = 0x10;
+        vip.registers[3] = 0x20;
+        vip.op(Instruction::JpV0 { vx: 3, nnn: 0x300 }).unwrap();
+        assert_eq!(vip.program_counter, 0x310); // V0-relative
+
+        let mut schip = VM::new(Quirks::schip());
+        schip.registers[0] = 0x10;
+        schip.registers[3] = 0x20;
+        schip.op(Instruction::JpV0 { vx: 3, nnn: 0x300 }).unwrap();
+        assert_eq!(schip.program_counter, 0x320); // Vx-relative
+    }
+
+    #[test]
+    fn load_store_quirk_chooses_whether_index_advances() {
+        let mut vip = VM::new(Quirks::chip8());
+        vip.index_register = 0x300;
+        vip.registers[0] = 1;
+        vip.registers[1] = 2;
+        vip.op(Instruction::LdIVx { vx: 1 }).unwrap();
+        assert_eq!(vip.index_register, 0x302); // advances past the last register stored
+        assert_eq!(vip.memory()[0x300], 1);
+        assert_eq!(vip.memory()[0x301], 2); // vx itself is stored, not dropped by the `0..=vx` range
+
+        let mut schip = VM::new(Quirks::schip());
+        schip.index_register = 0x300;
+        schip.registers[0] = 1;
+        schip.registers[1] = 2;
+        schip.op(Instruction::LdIVx { vx: 1 }).unwrap();
+        assert_eq!(schip.index_register, 0x300); // left untouched
+    }
+
+    #[test]
+    fn clip_sprites_quirk_chooses_clip_vs_wrap_at_screen_edge() {
+        let mut clipping = VM::new(Quirks::chip8());
+        clipping.memory[0x300] = 0xFF;
+        clipping.index_register = 0x300;
+        clipping.registers[0] = 63;
+        clipping.registers[1] = 0;
+        clipping.op(Instruction::Drw { vx: 0, vy: 1, n: 1 }).unwrap();
+        assert!(clipping.framebuffer.get(63, 0));
+        assert!(!clipping.framebuffer.get(0, 0)); // off-screen columns clipped, not wrapped
+
+        let mut quirks = Quirks::chip8();
+        quirks.clip_sprites = false;
+        let mut wrapping = VM::new(quirks);
+        wrapping.memory[0x300] = 0xFF;
+        wrapping.index_register = 0x300;
+        wrapping.registers[0] = 63;
+        wrapping.registers[1] = 0;
+        wrapping.op(Instruction::Drw { vx: 0, vy: 1, n: 1 }).unwrap();
+        assert!(wrapping.framebuffer.get(63, 0));
+        assert!(wrapping.framebuffer.get(0, 0)); // off-screen columns wrap around
+    }
+
+    #[test]
+    fn call_then_ret_restores_the_return_address() {
+        let mut vm = VM::new(Quirks::default());
+        vm.program_counter = 0x200;
+        vm.op(Instruction::Call { nnn: 0x300 }).unwrap();
+        assert_eq!(vm.program_counter, 0x300);
+
+        vm.op(Instruction::Ret).unwrap();
+        assert_eq!(vm.program_counter, 0x200); // restored, not the stale initial value
+    }
+
+    #[test]
+    fn nested_calls_return_to_the_correct_frame() {
+        let mut vm = VM::new(Quirks::default());
+        vm.program_counter = 0x200;
+        vm.op(Instruction::Call { nnn: 0x300 }).unwrap(); // outer call
+        vm.program_counter = 0x300;
+        vm.op(Instruction::Call { nnn: 0x400 }).unwrap(); // inner call
+
+        vm.op(Instruction::Ret).unwrap();
+        assert_eq!(vm.program_counter, 0x300); // inner frame's return address, not outer's
+
+        vm.op(Instruction::Ret).unwrap();
+        assert_eq!(vm.program_counter, 0x200); // outer frame's return address
+    }
+
+    #[test]
+    fn stack_overflow_when_call_exceeds_stack_depth() {
+        let mut vm = VM::new(Quirks::default());
+        vm.stack_pointer = vm.stack.len() as u8;
+        assert_eq!(vm.op(Instruction::Call { nnn: 0x300 }), Err(Trap::StackOverflow));
+    }
+
+    #[test]
+    fn stack_underflow_when_ret_on_empty_stack() {
+        let mut vm = VM::new(Quirks::default());
+        assert_eq!(vm.op(Instruction::Ret), Err(Trap::StackUnderflow));
+    }
+
+    #[test]
+    fn illegal_opcode_trap_carries_the_faulting_opcode() {
+        let mut vm = VM::new(Quirks::default());
+        assert_eq!(
+            vm.op(Instruction::Unknown { opcode: 0x5001 }),
+            Err(Trap::IllegalOpcode(0x5001))
+        );
+    }
+
+    #[test]
+    fn illegal_key_trap_on_out_of_range_key() {
+        let mut vm = VM::new(Quirks::default());
+        vm.registers[0] = 200;
+        assert_eq!(vm.op(Instruction::Skp { vx: 0 }), Err(Trap::IllegalKey(200)));
+    }
+
+    #[test]
+    fn memory_out_of_bounds_trap_on_fetch_past_memory_end() {
+        let vm = VM::new(Quirks::default());
+        assert!(matches!(
+            vm.peek_instruction(0xFFF),
+            Err(Trap::MemoryOutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn on_trap_skip_keeps_running_while_halt_propagates() {
+        let mut skip_vm = VM::new(Quirks::default());
+        skip_vm.memory[0x200] = 0x50;
+        skip_vm.memory[0x201] = 0x01; // unknown opcode: 0x5 group only defines low nibble 0x0
+        skip_vm.on_trap(|_trap| TrapAction::Skip);
+        assert_eq!(skip_vm.cycle(), Ok(()));
+        assert_eq!(skip_vm.program_counter, 0x202); // pc still advanced past the faulting opcode
+
+        let mut halt_vm = VM::new(Quirks::default());
+        halt_vm.memory[0x200] = 0x50;
+        halt_vm.memory[0x201] = 0x01;
+        halt_vm.on_trap(|_trap| TrapAction::Halt);
+        assert_eq!(halt_vm.cycle(), Err(Trap::IllegalOpcode(0x5001)));
+    }
+
+    #[test]
+    fn on_trap_continue_retries_a_decode_fault_while_skip_moves_past_it() {
+        let mut continue_vm = VM::new(Quirks::default());
+        continue_vm.program_counter = 0xFFF; // fetch runs off the end of memory
+        continue_vm.on_trap(|_trap| TrapAction::Continue);
+        assert_eq!(continue_vm.cycle(), Ok(()));
+        assert_eq!(continue_vm.program_counter, 0xFFF); // unchanged: same fault next cycle
+
+        let mut skip_vm = VM::new(Quirks::default());
+        skip_vm.program_counter = 0xFFF;
+        skip_vm.on_trap(|_trap| TrapAction::Skip);
+        assert_eq!(skip_vm.cycle(), Ok(()));
+        assert_eq!(skip_vm.program_counter, 0x1001); // forced past the unfetchable instruction
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+
+use crate::chip8::VM;
+use crate::instruction;
+use crate::trap::Trap;
+
+// Wraps a VM with breakpoints and instruction-level introspection, so a ROM author can trace
+// why a game misbehaves instead of only ever seeing it run (or crash) start to finish.
+pub struct Debugger {
+    vm: VM,
+    breakpoints: HashSet<u16>,
+}
+
+impl Debugger {
+    pub fn new(vm: VM) -> Debugger {
+        Debugger {
+            vm,
+            breakpoints: HashSet::new(),
+        }
+    }
+
+    pub fn vm(&self) -> &VM {
+        &self.vm
+    }
+
+    pub fn vm_mut(&mut self) -> &mut VM {
+        &mut self.vm
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) {
+        self.breakpoints.remove(&address);
+    }
+
+    // Execute exactly one instruction
+    pub fn step(&mut self) -> Result<(), Trap> {
+        self.vm.cycle()
+    }
+
+    // Whether the program counter is currently sitting on a breakpoint
+    pub fn at_breakpoint(&self) -> bool {
+        self.breakpoints.contains(&self.vm.program_counter())
+    }
+
+    // Disassemble `count` instructions starting `before` instructions back from the current
+    // program counter, e.g. to print a window of code centered on where execution is paused
+    pub fn disassemble_window(&self, before: u16, count: u16) -> Vec<(u16, String)> {
+        let start = self.vm.program_counter().saturating_sub(before * 2);
+
+        (0..count)
+            .filter_map(|i| {
+                let address = start + i * 2;
+                let instruction = self.vm.peek_instruction(address).ok()?;
+                Some((address, instruction::disassemble(&instruction)))
+            })
+            .collect()
+    }
+}
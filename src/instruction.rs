@@ -0,0 +1,197 @@
+// Which instruction family an opcode belongs to. This is looked up from a table generated at
+// build time by `build.rs` (see `OPCODE_TABLE` in `chip8.rs`) so decoding a 16-bit opcode is a
+// single array index instead of a hand-written nibble match.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OpKind {
+    Cls,
+    Ret,
+    AndReg,
+    Sys,
+    Jp,
+    Call,
+    SeByte,
+    SneByte,
+    SeReg,
+    LdByte,
+    AddByte,
+    LdReg,
+    OrReg,
+    XorReg,
+    AddReg,
+    SubReg,
+    Shr,
+    Subn,
+    Shl,
+    SneReg,
+    LdI,
+    JpV0,
+    Rnd,
+    Drw,
+    Skp,
+    Sknp,
+    LdVxDt,
+    LdVxK,
+    LdDtVx,
+    LdStVx,
+    AddIVx,
+    LdFVx,
+    LdBVx,
+    LdIVx,
+    LdVxI,
+    Unknown,
+}
+
+// A fully decoded opcode: the instruction family plus whichever operands it needs
+#[derive(Copy, Clone, Debug)]
+pub enum Instruction {
+    Cls,
+    Ret,
+    AndReg { vx: usize, vy: usize },
+    Sys,
+    Jp { nnn: u16 },
+    Call { nnn: u16 },
+    SeByte { vx: usize, byte: u8 },
+    SneByte { vx: usize, byte: u8 },
+    SeReg { vx: usize, vy: usize },
+    LdByte { vx: usize, byte: u8 },
+    AddByte { vx: usize, byte: u8 },
+    LdReg { vx: usize, vy: usize },
+    OrReg { vx: usize, vy: usize },
+    XorReg { vx: usize, vy: usize },
+    AddReg { vx: usize, vy: usize },
+    SubReg { vx: usize, vy: usize },
+    Shr { vx: usize, vy: usize },
+    Subn { vx: usize, vy: usize },
+    Shl { vx: usize, vy: usize },
+    SneReg { vx: usize, vy: usize },
+    LdI { nnn: u16 },
+    JpV0 { vx: usize, nnn: u16 },
+    Rnd { vx: usize, byte: u8 },
+    Drw { vx: usize, vy: usize, n: u8 },
+    Skp { vx: usize },
+    Sknp { vx: usize },
+    LdVxDt { vx: usize },
+    LdVxK { vx: usize },
+    LdDtVx { vx: usize },
+    LdStVx { vx: usize },
+    AddIVx { vx: usize },
+    LdFVx { vx: usize },
+    LdBVx { vx: usize },
+    LdIVx { vx: usize },
+    LdVxI { vx: usize },
+    Unknown { opcode: u16 },
+}
+
+// Combine an opcode family looked up from `OPCODE_TABLE` with the raw opcode's operand bits
+pub fn decode(opcode: u16, kind: OpKind) -> Instruction {
+    let nnn = opcode & 0x0FFF;
+    let vx = ((opcode & 0x0F00) >> 8) as usize;
+    let vy = ((opcode & 0x00F0) >> 4) as usize;
+    let byte = (opcode & 0x00FF) as u8;
+    let n = (opcode & 0x000F) as u8;
+
+    match kind {
+        OpKind::Cls => Instruction::Cls,
+        OpKind::Ret => Instruction::Ret,
+        OpKind::AndReg => Instruction::AndReg { vx, vy },
+        OpKind::Sys => Instruction::Sys,
+        OpKind::Jp => Instruction::Jp { nnn },
+        OpKind::Call => Instruction::Call { nnn },
+        OpKind::SeByte => Instruction::SeByte { vx, byte },
+        OpKind::SneByte => Instruction::SneByte { vx, byte },
+        OpKind::SeReg => Instruction::SeReg { vx, vy },
+        OpKind::LdByte => Instruction::LdByte { vx, byte },
+        OpKind::AddByte => Instruction::AddByte { vx, byte },
+        OpKind::LdReg => Instruction::LdReg { vx, vy },
+        OpKind::OrReg => Instruction::OrReg { vx, vy },
+        OpKind::XorReg => Instruction::XorReg { vx, vy },
+        OpKind::AddReg => Instruction::AddReg { vx, vy },
+        OpKind::SubReg => Instruction::SubReg { vx, vy },
+        OpKind::Shr => Instruction::Shr { vx, vy },
+        OpKind::Subn => Instruction::Subn { vx, vy },
+        OpKind::Shl => Instruction::Shl { vx, vy },
+        OpKind::SneReg => Instruction::SneReg { vx, vy },
+        OpKind::LdI => Instruction::LdI { nnn },
+        OpKind::JpV0 => Instruction::JpV0 { vx, nnn },
+        OpKind::Rnd => Instruction::Rnd { vx, byte },
+        OpKind::Drw => Instruction::Drw { vx, vy, n },
+        OpKind::Skp => Instruction::Skp { vx },
+        OpKind::Sknp => Instruction::Sknp { vx },
+        OpKind::LdVxDt => Instruction::LdVxDt { vx },
+        OpKind::LdVxK => Instruction::LdVxK { vx },
+        OpKind::LdDtVx => Instruction::LdDtVx { vx },
+        OpKind::LdStVx => Instruction::LdStVx { vx },
+        OpKind::AddIVx => Instruction::AddIVx { vx },
+        OpKind::LdFVx => Instruction::LdFVx { vx },
+        OpKind::LdBVx => Instruction::LdBVx { vx },
+        OpKind::LdIVx => Instruction::LdIVx { vx },
+        OpKind::LdVxI => Instruction::LdVxI { vx },
+        OpKind::Unknown => Instruction::Unknown { opcode },
+    }
+}
+
+// Render a decoded instruction as its mnemonic form, e.g. `0x6A02` -> `LD VA, 0x02`
+pub fn disassemble(instruction: &Instruction) -> String {
+    match *instruction {
+        Instruction::Cls => "CLS".to_string(),
+        Instruction::Ret => "RET".to_string(),
+        Instruction::AndReg { vx, vy } => format!("AND V{:X}, V{:X}", vx, vy),
+        Instruction::Sys => "SYS".to_string(),
+        Instruction::Jp { nnn } => format!("JP {:#05X}", nnn),
+        Instruction::Call { nnn } => format!("CALL {:#05X}", nnn),
+        Instruction::SeByte { vx, byte } => format!("SE V{:X}, {:#04X}", vx, byte),
+        Instruction::SneByte { vx, byte } => format!("SNE V{:X}, {:#04X}", vx, byte),
+        Instruction::SeReg { vx, vy } => format!("SE V{:X}, V{:X}", vx, vy),
+        Instruction::LdByte { vx, byte } => format!("LD V{:X}, {:#04X}", vx, byte),
+        Instruction::AddByte { vx, byte } => format!("ADD V{:X}, {:#04X}", vx, byte),
+        Instruction::LdReg { vx, vy } => format!("LD V{:X}, V{:X}", vx, vy),
+        Instruction::OrReg { vx, vy } => format!("OR V{:X}, V{:X}", vx, vy),
+        Instruction::XorReg { vx, vy } => format!("XOR V{:X}, V{:X}", vx, vy),
+        Instruction::AddReg { vx, vy } => format!("ADD V{:X}, V{:X}", vx, vy),
+        Instruction::SubReg { vx, vy } => format!("SUB V{:X}, V{:X}", vx, vy),
+        Instruction::Shr { vx, vy } => format!("SHR V{:X}, V{:X}", vx, vy),
+        Instruction::Subn { vx, vy } => format!("SUBN V{:X}, V{:X}", vx, vy),
+        Instruction::Shl { vx, vy } => format!("SHL V{:X}, V{:X}", vx, vy),
+        Instruction::SneReg { vx, vy } => format!("SNE V{:X}, V{:X}", vx, vy),
+        Instruction::LdI { nnn } => format!("LD I, {:#05X}", nnn),
+        Instruction::JpV0 { vx, nnn } => format!("JP V{:X}, {:#05X}", vx, nnn),
+        Instruction::Rnd { vx, byte } => format!("RND V{:X}, {:#04X}", vx, byte),
+        Instruction::Drw { vx, vy, n } => format!("DRW V{:X}, V{:X}, {}", vx, vy, n),
+        Instruction::Skp { vx } => format!("SKP V{:X}", vx),
+        Instruction::Sknp { vx } => format!("SKNP V{:X}", vx),
+        Instruction::LdVxDt { vx } => format!("LD V{:X}, DT", vx),
+        Instruction::LdVxK { vx } => format!("LD V{:X}, K", vx),
+        Instruction::LdDtVx { vx } => format!("LD DT, V{:X}", vx),
+        Instruction::LdStVx { vx } => format!("LD ST, V{:X}", vx),
+        Instruction::AddIVx { vx } => format!("ADD I, V{:X}", vx),
+        Instruction::LdFVx { vx } => format!("LD F, V{:X}", vx),
+        Instruction::LdBVx { vx } => format!("LD B, V{:X}", vx),
+        Instruction::LdIVx { vx } => format!("LD [I], V{:X}", vx),
+        Instruction::LdVxI { vx } => format!("LD V{:X}, [I]", vx),
+        Instruction::Unknown { opcode } => format!("??? {:#06X}", opcode),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_then_disassemble_round_trips_to_the_expected_mnemonic() {
+        let cases = [
+            (0x00E0, OpKind::Cls, "CLS"),
+            (0x1234, OpKind::Jp, "JP 0x234"),
+            (0x6A02, OpKind::LdByte, "LD VA, 0x02"),
+            (0x8120, OpKind::LdReg, "LD V1, V2"),
+            (0x812E, OpKind::Shl, "SHL V1, V2"),
+            (0xD123, OpKind::Drw, "DRW V1, V2, 3"),
+            (0xFF1E, OpKind::AddIVx, "ADD I, VF"),
+            (0xF000, OpKind::Unknown, "??? 0xF000"),
+        ];
+
+        for (opcode, kind, expected_mnemonic) in cases {
+            let instruction = decode(opcode, kind);
+            assert_eq!(disassemble(&instruction), expected_mnemonic);
+        }
+    }
+}
@@ -1,15 +1,96 @@
+use std::env;
 use std::fs::File;
 use std::io::prelude::*;
+use std::path::Path;
 
+mod app;
+mod audio;
 mod chip8;
+mod debugger;
+mod instruction;
+mod quirks;
+mod savestate;
+mod trap;
+
+// Framebuffer pixels are scaled up by this factor for display
+const DEFAULT_SCALE: u32 = 10;
+
+// Number of VM cycles to execute per rendered frame, absent `--cycles`
+const DEFAULT_CYCLES_PER_FRAME: u32 = 10;
+
+// Parse `--quirks <chip8|schip>` and `--cycles <n>` out of the command line, leaving the ROM
+// path as the only remaining positional argument. Falls back to `Quirks::default()` (original
+// COSMAC VIP semantics) and `DEFAULT_CYCLES_PER_FRAME` if a flag is absent or its value isn't
+// recognized.
+fn parse_args(args: impl Iterator<Item = String>) -> (Option<String>, quirks::Quirks, u32) {
+    let mut quirks = quirks::Quirks::default();
+    let mut cycles_per_frame = DEFAULT_CYCLES_PER_FRAME;
+    let mut rom_path = None;
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        if arg == "--quirks" {
+            match args.next().as_deref() {
+                Some("schip") => quirks = quirks::Quirks::schip(),
+                Some("chip8") => quirks = quirks::Quirks::default(),
+                Some(other) => eprintln!("Unknown --quirks preset '{}', using chip8", other),
+                None => eprintln!("--quirks requires a value (chip8 or schip)"),
+            }
+        } else if arg == "--cycles" {
+            match args.next().as_deref().map(|n| n.parse()) {
+                Some(Ok(n)) => cycles_per_frame = n,
+                Some(Err(_)) => eprintln!("--cycles requires a positive integer"),
+                None => eprintln!("--cycles requires a value"),
+            }
+        } else if rom_path.is_none() {
+            rom_path = Some(arg);
+        }
+    }
+
+    (rom_path, quirks, cycles_per_frame)
+}
 
 fn main() {
+    let (rom_path, quirks, cycles_per_frame) = parse_args(env::args().skip(1));
+    let rom_path = rom_path.unwrap_or_else(|| "test.rom".to_string());
+
+    let rom_name = Path::new(&rom_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("game")
+        .to_string();
+
     let mut rom: [u8; 3584] = [0; 3584];
-    let mut file = File::open("test.rom").unwrap();
+    let mut file = File::open(rom_path).unwrap();
 
     file.read(&mut rom).unwrap();
 
-    let mut vm = chip8::VM::new();
+    let mut vm = chip8::VM::new(quirks);
     vm.load(rom);
-    vm.cycle();
+
+    // Log every trap. Faults that leave the machine in a corrupt state (a blown stack, an
+    // out-of-bounds memory access) halt so the front end can show the debugger instead of
+    // continuing to execute garbage. An unknown opcode is skipped -- its encoding is forced
+    // past so one bad ROM byte doesn't end the session -- while a key index out of the 0x0-0xF
+    // range is a no-op (Skp/Sknp just never matches), so it continues without forcing the
+    // program counter anywhere.
+    vm.on_trap(|trap| {
+        eprintln!("Trap: {:?}", trap);
+
+        match trap {
+            trap::Trap::StackOverflow
+            | trap::Trap::StackUnderflow
+            | trap::Trap::MemoryOutOfBounds
+            | trap::Trap::CorruptSaveState => trap::TrapAction::Halt,
+            trap::Trap::IllegalOpcode(_) => trap::TrapAction::Skip,
+            trap::Trap::IllegalKey(_) => trap::TrapAction::Continue,
+        }
+    });
+
+    let mut debugger = debugger::Debugger::new(vm);
+
+    let sdl_context = sdl2::init().unwrap();
+    let mut app = app::App::new(&sdl_context, DEFAULT_SCALE, rom_name, cycles_per_frame);
+
+    app.run(&mut debugger);
 }
@@ -0,0 +1,43 @@
+// Several CHIP-8 opcodes are ambiguous between interpreter generations; `Quirks` selects
+// which behavior `VM::op` uses so a single core can run ROMs authored for either.
+pub struct Quirks {
+    // 8XY6/8XYE: shift Vx in place (true) vs. shift Vy into Vx first (false, original COSMAC VIP)
+    pub shift_in_place: bool,
+
+    // FX55/FX65: increment `index_register` as the load/store loop runs
+    pub load_store_increments_index: bool,
+
+    // BNNN: jump to Vx + NNN (true, SUPER-CHIP) vs. V0 + NNN (false, original COSMAC VIP)
+    pub jump_uses_vx: bool,
+
+    // DXYN: clip sprites at the screen edge (true) vs. wrap them around (false)
+    pub clip_sprites: bool,
+}
+
+impl Quirks {
+    // Original COSMAC VIP CHIP-8 interpreter semantics
+    pub fn chip8() -> Quirks {
+        Quirks {
+            shift_in_place: false,
+            load_store_increments_index: true,
+            jump_uses_vx: false,
+            clip_sprites: true,
+        }
+    }
+
+    // SUPER-CHIP / most modern interpreters
+    pub fn schip() -> Quirks {
+        Quirks {
+            shift_in_place: true,
+            load_store_increments_index: false,
+            jump_uses_vx: true,
+            clip_sprites: true,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks::chip8()
+    }
+}
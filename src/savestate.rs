@@ -0,0 +1,84 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Write a snapshot to `<rom_name>-<unix timestamp>.state`, e.g. `game-1718838271.state`
+pub fn save(rom_name: &str, state: &[u8]) -> std::io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let path = PathBuf::from(format!("{}-{}.state", rom_name, timestamp));
+    fs::write(&path, state)?;
+
+    Ok(path)
+}
+
+// Find the most recently modified snapshot for `rom_name` in `dir`, regardless of its exact
+// timestamp
+pub fn find_latest(dir: &Path, rom_name: &str) -> Option<PathBuf> {
+    let prefix = format!("{}-", rom_name);
+
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "state")
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|stem| stem.starts_with(&prefix))
+        })
+        .max_by_key(|path| {
+            fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .unwrap_or(UNIX_EPOCH)
+        })
+}
+
+// Load the most recent snapshot for `rom_name` in `dir`, if one exists
+pub fn load_latest(dir: &Path, rom_name: &str) -> Option<Vec<u8>> {
+    let path = find_latest(dir, rom_name)?;
+    fs::read(path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    #[test]
+    fn find_latest_picks_most_recently_modified_candidate() {
+        let dir = std::env::temp_dir().join(format!("chip8_find_latest_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        File::create(dir.join("game-100.state")).unwrap();
+        File::create(dir.join("game-200.state")).unwrap();
+        File::create(dir.join("other-300.state")).unwrap(); // different rom_name, must be ignored
+
+        File::open(dir.join("game-100.state"))
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(100))
+            .unwrap();
+        File::open(dir.join("game-200.state"))
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(200))
+            .unwrap();
+        File::open(dir.join("other-300.state"))
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(300))
+            .unwrap();
+
+        let result = find_latest(&dir, "game");
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(
+            result.unwrap().file_name().unwrap().to_str().unwrap(),
+            "game-200.state"
+        );
+    }
+}
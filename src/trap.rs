@@ -0,0 +1,26 @@
+// A structured fault raised by `VM::cycle` (or another `VM` operation on untrusted input,
+// like `load_state`) instead of panicking or silently ignoring the condition. Lets an
+// embedder (e.g. a debugger front end) decide what happens next.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Trap {
+    StackOverflow,
+    StackUnderflow,
+    IllegalOpcode(u16),
+    IllegalKey(u8),
+    MemoryOutOfBounds,
+    // `load_state` was handed a blob that isn't exactly `VM::STATE_LEN` bytes -- truncated,
+    // corrupted, or from an incompatible build
+    CorruptSaveState,
+}
+
+// What the embedder's trap handler wants `VM::cycle` to do next
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrapAction {
+    // Stop and propagate the trap to the caller
+    Halt,
+    // Force the program counter past the faulting instruction and keep running
+    Skip,
+    // Leave the program counter exactly where the fault left it and keep running -- for a
+    // fault raised during decode this retries the same instruction next cycle
+    Continue,
+}